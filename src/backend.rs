@@ -0,0 +1,81 @@
+use anyhow::Result;
+
+/// Workgroup tile geometry, chosen at startup to match the active backend.
+///
+/// `local_size * local_size` must stay under the backend's invocation limit,
+/// and each dispatch consumes a halo of `steps_per_dispatch` cells on every
+/// side of the tile, so `tile_output_size()` must stay positive.
+#[derive(Copy, Clone, Debug)]
+pub struct TileGeometry {
+    pub local_size: usize,
+    pub steps_per_dispatch: usize,
+}
+
+impl TileGeometry {
+    /// Number of steps each solver dispatch comprises, absent any backend-specific override
+    pub const DEFAULT_STEPS_PER_DISPATCH: usize = 5;
+
+    /// Each tile outputs information with this width once its halo is discarded
+    pub fn tile_output_size(&self) -> usize {
+        self.local_size - self.steps_per_dispatch * 2
+    }
+}
+
+/// Storage-image access mode for a resource bound to a dispatch.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ImageAccess {
+    ReadOnly,
+    WriteOnly,
+    ReadWrite,
+}
+
+/// The subset of compute operations `LinSolve` needs from a GPU backend.
+///
+/// Implemented once on top of raw `glow` + desktop GL (`GlBackend`, the
+/// default) and once on top of `wgpu` (`WgpuBackend`), so the same Jacobi
+/// solve can run on desktop OpenGL or on Vulkan/Metal/DX12/WebGPU without
+/// `LinSolve` itself knowing which.
+pub trait ComputeBackend {
+    /// An opaque handle to a single-channel (`R32F`) 2D image.
+    type Image: Clone;
+
+    /// The tile geometry this backend's compute pipeline was specialized for.
+    fn tile_geometry(&self) -> TileGeometry;
+
+    /// Create a single-channel float image, optionally initialized from `pixels`.
+    fn create_image(
+        &self,
+        width: usize,
+        height: usize,
+        pixels: Option<&[f32]>,
+    ) -> Result<Self::Image>;
+
+    /// Bind `image` as a storage image at `binding` for the next dispatch.
+    fn bind_image(&self, binding: u32, image: &Self::Image, access: ImageAccess);
+
+    /// Dispatch the solver kernel over an `x_tiles` x `y_tiles` grid of workgroups.
+    fn dispatch(&self, x_tiles: u32, y_tiles: u32);
+
+    /// Insert a barrier ensuring image writes from the prior dispatch are
+    /// visible to the next one's reads/writes.
+    fn memory_barrier(&self);
+
+    /// Upload `pixels` into `image`, replacing its contents.
+    fn upload(&self, image: &Self::Image, width: usize, height: usize, pixels: &[f32]);
+
+    /// Set the Jacobi update's `a`/`c` coefficients and `bounds` selector for
+    /// every dispatch until the next call. `LinSolve::step` calls this once
+    /// per step, before its dispatch loop.
+    fn set_uniforms(&self, a: f32, c: f32, bounds: i32);
+
+    /// Read `image` back into a freshly-allocated buffer of `width * height` floats.
+    fn download(&self, image: &Self::Image, width: usize, height: usize) -> Vec<f32>;
+
+    /// Measure the global residual accumulated by the last dispatch, for
+    /// backends that implement the subgroup-reduction early exit used by
+    /// `LinSolve::step`'s tolerance mode. Backends without a reduction path
+    /// (currently: `WgpuBackend`) return `Ok(None)`.
+    fn measure_residual(&self) -> Result<Option<f32>> {
+        Ok(None)
+    }
+}
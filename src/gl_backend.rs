@@ -0,0 +1,200 @@
+use crate::backend::{ComputeBackend, ImageAccess, TileGeometry};
+use crate::gl_utils::*;
+use anyhow::{bail, Result};
+use glow::{Context as GlContext, HasContext, NativeProgram, NativeTexture, NativeUniformLocation};
+
+/// The desktop backend: raw `glow` + SDL2. This is the crate's original
+/// implementation and remains the default `ComputeBackend`.
+pub struct GlBackend<'a> {
+    gl: &'a GlContext,
+    /// Compiled `kernels/lin_solve.comp`, specialized for `tile_geometry`
+    program: NativeProgram,
+    /// Single-texel R32UI accumulator the kernel reduces the per-dispatch
+    /// residual into; cleared and re-bound on every `dispatch`.
+    residual_accum: NativeTexture,
+    tile_geometry: TileGeometry,
+    /// Locations of `lin_solve.comp`'s `a`/`c`/`bounds` uniforms, queried once
+    /// at link time and set on every `set_uniforms` call.
+    uniform_a: Option<NativeUniformLocation>,
+    uniform_c: Option<NativeUniformLocation>,
+    uniform_bounds: Option<NativeUniformLocation>,
+}
+
+impl<'a> GlBackend<'a> {
+    /// Query the driver and compile `kernels/lin_solve.comp`, specialized for
+    /// a tile size tuned to it.
+    ///
+    /// Mesa's open-source compute drivers (RadeonSI, RADV) spill shared
+    /// memory once this kernel's tile exceeds 16x16 invocations; other
+    /// vendors handle the larger 32x32 tile comfortably, so prefer that
+    /// everywhere else.
+    pub fn new(gl: &'a GlContext) -> Result<Self> {
+        let tile_geometry = Self::detect_tile_geometry(gl)?;
+
+        let program = create_program(
+            gl,
+            &[(glow::COMPUTE_SHADER, "./kernels/lin_solve.comp")],
+            &[
+                ("LOCAL_SIZE", tile_geometry.local_size.to_string()),
+                (
+                    "STEPS_PER_DISPATCH",
+                    tile_geometry.steps_per_dispatch.to_string(),
+                ),
+            ],
+        )?;
+
+        let residual_accum = create_u32_accumulator(gl)?;
+
+        let (uniform_a, uniform_c, uniform_bounds) = unsafe {
+            (
+                gl.get_uniform_location(program, "a"),
+                gl.get_uniform_location(program, "c"),
+                gl.get_uniform_location(program, "bounds"),
+            )
+        };
+
+        Ok(Self {
+            gl,
+            program,
+            residual_accum,
+            tile_geometry,
+            uniform_a,
+            uniform_c,
+            uniform_bounds,
+        })
+    }
+
+    fn detect_tile_geometry(gl: &GlContext) -> Result<TileGeometry> {
+        unsafe {
+            let vendor = gl.get_parameter_string(glow::VENDOR);
+            let max_invocations =
+                gl.get_parameter_i32(glow::MAX_COMPUTE_WORK_GROUP_INVOCATIONS) as usize;
+
+            let steps_per_dispatch = TileGeometry::DEFAULT_STEPS_PER_DISPATCH;
+            let preferred_local_size = if vendor.contains("AMD") || vendor.contains("Mesa") {
+                16
+            } else {
+                32
+            };
+
+            let max_local_size = (max_invocations as f64).sqrt() as usize;
+            if max_local_size <= steps_per_dispatch * 2 {
+                bail!(
+                    "MAX_COMPUTE_WORK_GROUP_INVOCATIONS ({}) is too small for a {}-step halo",
+                    max_invocations,
+                    steps_per_dispatch,
+                );
+            }
+
+            Ok(TileGeometry {
+                local_size: preferred_local_size.min(max_local_size),
+                steps_per_dispatch,
+            })
+        }
+    }
+
+    fn gl_access(access: ImageAccess) -> u32 {
+        match access {
+            ImageAccess::ReadOnly => glow::READ_ONLY,
+            ImageAccess::WriteOnly => glow::WRITE_ONLY,
+            ImageAccess::ReadWrite => glow::READ_WRITE,
+        }
+    }
+}
+
+impl<'a> ComputeBackend for GlBackend<'a> {
+    type Image = NativeTexture;
+
+    fn tile_geometry(&self) -> TileGeometry {
+        self.tile_geometry
+    }
+
+    fn create_image(
+        &self,
+        width: usize,
+        height: usize,
+        pixels: Option<&[f32]>,
+    ) -> Result<Self::Image> {
+        create_image(self.gl, width as i32, height as i32, pixels)
+    }
+
+    fn bind_image(&self, binding: u32, image: &Self::Image, access: ImageAccess) {
+        unsafe {
+            self.gl.use_program(Some(self.program));
+            self.gl
+                .bind_image_texture(binding, *image, 0, false, 0, Self::gl_access(access), glow::R32F);
+        }
+    }
+
+    fn dispatch(&self, x_tiles: u32, y_tiles: u32) {
+        const RESIDUAL_BIND: u32 = 4;
+
+        unsafe {
+            clear_u32_accumulator(self.gl, self.residual_accum);
+            self.gl.bind_image_texture(
+                RESIDUAL_BIND,
+                self.residual_accum,
+                0,
+                false,
+                0,
+                glow::READ_WRITE,
+                glow::R32UI,
+            );
+
+            self.gl.dispatch_compute(x_tiles, y_tiles, 1);
+        }
+    }
+
+    fn memory_barrier(&self) {
+        unsafe {
+            self.gl
+                .memory_barrier(glow::SHADER_STORAGE_BARRIER_BIT | glow::SHADER_IMAGE_ACCESS_BARRIER_BIT);
+        }
+    }
+
+    fn upload(&self, image: &Self::Image, width: usize, height: usize, pixels: &[f32]) {
+        unsafe {
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(*image));
+            self.gl.tex_sub_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                glow::RED,
+                glow::FLOAT,
+                Some(bytemuck::cast_slice(pixels)),
+            );
+        }
+    }
+
+    fn download(&self, image: &Self::Image, width: usize, height: usize) -> Vec<f32> {
+        let mut data = vec![0f32; width * height];
+        unsafe {
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(*image));
+            self.gl.get_tex_image(
+                glow::TEXTURE_2D,
+                0,
+                glow::RED,
+                glow::FLOAT,
+                glow::PixelPackData::Slice(bytemuck::cast_slice_mut(&mut data)),
+            );
+        }
+        data
+    }
+
+    fn measure_residual(&self) -> Result<Option<f32>> {
+        let bits = AsyncTexelReadback::request(self.gl, self.residual_accum)?.take_blocking(self.gl);
+        Ok(Some(f32::from_bits(bits)))
+    }
+
+    fn set_uniforms(&self, a: f32, c: f32, bounds: i32) {
+        unsafe {
+            self.gl.use_program(Some(self.program));
+            self.gl.uniform_1_f32(self.uniform_a.as_ref(), a);
+            self.gl.uniform_1_f32(self.uniform_c.as_ref(), c);
+            self.gl.uniform_1_i32(self.uniform_bounds.as_ref(), bounds);
+        }
+    }
+}
@@ -1,13 +1,55 @@
 use anyhow::{bail, format_err, Context as AnyhowContext, Result};
-use glow::{Context as GlContext, HasContext, NativeBuffer, NativeProgram, NativeTexture, PixelPackData};
-use std::path::Path;
+use fruid::Array2D;
+use glow::{
+    Context as GlContext, HasContext, NativeBuffer, NativeFence, NativeProgram, NativeTexture,
+    PixelPackData,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
 
-/// Compile and link program from sources
+/// Linked program binaries are cached here, keyed on source + driver identity.
+/// Lives under `target/` so the existing gitignore entry covers it.
+pub(crate) const PROGRAM_CACHE_DIR: &str = "target/gl_program_cache";
+
+/// Compile and link program from sources.
+///
+/// `defines` is a set of key/value substitutions, each emitted as
+/// `#define key value` and spliced into every shader immediately after its
+/// `#version` line (GLSL requires `#version` to stay the first line). This
+/// lets callers bake runtime-chosen constants such as workgroup size into an
+/// otherwise-static `.comp` file.
+///
+/// A successful link is cached to disk as a `glGetProgramBinary` blob, keyed
+/// on a hash of the (already-specialized) source text plus `GL_VENDOR` /
+/// `GL_RENDERER`, so subsequent calls with the same key skip GLSL compilation
+/// entirely via `glProgramBinary`. Any cache miss or driver rejection (e.g.
+/// `GL_PROGRAM_BINARY_FORMATS` changed) transparently falls back to compiling
+/// from source.
 pub fn create_program<P: AsRef<Path>>(
     gl: &GlContext,
     shader_sources: &[(u32, P)],
+    defines: &[(&str, String)],
 ) -> Result<NativeProgram> {
     unsafe {
+        let preamble: String = defines
+            .iter()
+            .map(|(name, value)| format!("#define {} {}\n", name, value))
+            .collect();
+
+        let mut sources = Vec::with_capacity(shader_sources.len());
+        for (_, shader_path) in shader_sources.iter() {
+            let raw = std::fs::read_to_string(shader_path)
+                .with_context(|| format!("Failed to read {}", shader_path.as_ref().display()))?;
+            sources.push(splice_defines(&raw, &preamble));
+        }
+
+        let cache_key = program_cache_key(gl, &sources);
+
+        if let Some(program) = load_cached_program(gl, cache_key) {
+            return Ok(program);
+        }
+
         let program = gl
             .create_program()
             .map_err(|e| format_err!("{:#}", e))
@@ -15,18 +57,14 @@ pub fn create_program<P: AsRef<Path>>(
 
         let mut shaders = Vec::with_capacity(shader_sources.len());
 
-        for (shader_type, shader_path) in shader_sources.iter() {
-            // Read
-            let shader_source = std::fs::read_to_string(shader_path)
-                .with_context(|| format!("Failed to read {}", shader_path.as_ref().display()))?;
-
+        for ((shader_type, _), shader_source) in shader_sources.iter().zip(&sources) {
             // Compile
             let shader = gl
                 .create_shader(*shader_type)
                 .map_err(|e| format_err!("{:#}", e))
                 .context("Cannot create program")?;
 
-            gl.shader_source(shader, &shader_source);
+            gl.shader_source(shader, shader_source);
             gl.compile_shader(shader);
 
             if !gl.get_shader_compile_status(shader) {
@@ -50,11 +88,90 @@ pub fn create_program<P: AsRef<Path>>(
             gl.delete_shader(shader);
         }
 
+        cache_program_binary(gl, cache_key, program);
+
         Ok(program)
     }
 }
 
-/// Create a single-channel float image with the given dimensions 
+/// Insert `preamble` right after the `#version` line of `source`, or at the
+/// very start if there isn't one.
+pub(crate) fn splice_defines(source: &str, preamble: &str) -> String {
+    if preamble.is_empty() {
+        return source.to_string();
+    }
+
+    match source.find('\n') {
+        Some(newline) if source[..newline].trim_start().starts_with("#version") => {
+            let (first_line, rest) = source.split_at(newline + 1);
+            format!("{first_line}{preamble}{rest}")
+        }
+        _ => format!("{preamble}{source}"),
+    }
+}
+
+/// Hash the final (post-`#define`) shader sources together with the active
+/// driver's identity, so binaries are never reused across incompatible
+/// drivers even if the source text happens to match.
+pub(crate) fn program_cache_key(gl: &GlContext, sources: &[String]) -> u64 {
+    unsafe {
+        let vendor = gl.get_parameter_string(glow::VENDOR);
+        let renderer = gl.get_parameter_string(glow::RENDERER);
+
+        let mut hasher = DefaultHasher::new();
+        sources.hash(&mut hasher);
+        vendor.hash(&mut hasher);
+        renderer.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+pub(crate) fn program_cache_path(cache_key: u64) -> PathBuf {
+    Path::new(PROGRAM_CACHE_DIR).join(format!("{cache_key:016x}.bin"))
+}
+
+/// Try to load a previously linked binary for `cache_key`. Returns `None` on
+/// any miss, read error, or rejection by `glProgramBinary` (the caller should
+/// fall back to full compilation in that case).
+pub(crate) unsafe fn load_cached_program(gl: &GlContext, cache_key: u64) -> Option<NativeProgram> {
+    let bytes = std::fs::read(program_cache_path(cache_key)).ok()?;
+    let format_bytes: [u8; 4] = bytes.get(..4)?.try_into().ok()?;
+    let format = u32::from_le_bytes(format_bytes);
+    let binary = &bytes[4..];
+
+    let program = gl.create_program().ok()?;
+    gl.program_binary(program, format, binary);
+
+    if gl.get_program_link_status(program) {
+        Some(program)
+    } else {
+        gl.delete_program(program);
+        None
+    }
+}
+
+/// Persist `program`'s linked binary under `cache_key` for future startups.
+/// Not every driver implements `glGetProgramBinary` for every program;
+/// failures here are non-fatal, they just mean the next launch recompiles.
+unsafe fn cache_program_binary(gl: &GlContext, cache_key: u64, program: NativeProgram) {
+    let Some((format, binary)) = gl.get_program_binary(program) else {
+        return;
+    };
+
+    let path = program_cache_path(cache_key);
+    if let Some(dir) = path.parent() {
+        if std::fs::create_dir_all(dir).is_err() {
+            return;
+        }
+    }
+
+    let mut contents = Vec::with_capacity(4 + binary.len());
+    contents.extend_from_slice(&format.to_le_bytes());
+    contents.extend_from_slice(&binary);
+    let _ = std::fs::write(path, contents);
+}
+
+/// Create a single-channel float image with the given dimensions
 pub fn create_image(
     gl: &GlContext,
     width: i32,
@@ -112,6 +229,51 @@ pub fn create_image(
     }
 }
 
+/// Create a 1x1 `R32UI` image used as a single-texel `imageAtomicMax`
+/// accumulator (e.g. for a residual reduction).
+pub fn create_u32_accumulator(gl: &GlContext) -> Result<NativeTexture> {
+    unsafe {
+        let tex = gl
+            .create_texture()
+            .map_err(|e| format_err!("{:#}", e))
+            .context("Cannot create accumulator texture")?;
+
+        gl.bind_texture(glow::TEXTURE_2D, Some(tex));
+        gl.bind_image_texture(0, tex, 0, false, 0, glow::READ_WRITE, glow::R32UI);
+        gl.tex_image_2d(
+            glow::TEXTURE_2D,
+            0,
+            glow::R32UI as _,
+            1,
+            1,
+            0,
+            glow::RED_INTEGER,
+            glow::UNSIGNED_INT,
+            None,
+        );
+
+        Ok(tex)
+    }
+}
+
+/// Reset a `create_u32_accumulator` texture back to zero.
+pub fn clear_u32_accumulator(gl: &GlContext, tex: NativeTexture) {
+    unsafe {
+        gl.bind_texture(glow::TEXTURE_2D, Some(tex));
+        gl.tex_sub_image_2d(
+            glow::TEXTURE_2D,
+            0,
+            0,
+            0,
+            1,
+            1,
+            glow::RED_INTEGER,
+            glow::UNSIGNED_INT,
+            Some(bytemuck::cast_slice(&[0u32])),
+        );
+    }
+}
+
 pub fn create_sdl2_context() -> (
     glow::Context,
     sdl2::video::Window,
@@ -140,3 +302,171 @@ pub fn create_sdl2_context() -> (
     }
 }
 
+/// A single in-flight, non-blocking readback of a single-channel float texture.
+///
+/// `request` queues a `glGetTexImage` into a `GL_PIXEL_PACK_BUFFER` and records
+/// a fence, returning immediately without stalling the compute pipeline.
+/// Callers should poll `try_take` once per frame; it returns `Err` holding the
+/// handle until the GPU has caught up to the fence.
+pub struct AsyncReadback {
+    pbo: NativeBuffer,
+    fence: NativeFence,
+    width: usize,
+    height: usize,
+}
+
+impl AsyncReadback {
+    /// Issue an async readback of `tex` (assumed `width` x `height`, `R32F`).
+    pub fn request(gl: &GlContext, tex: NativeTexture, width: usize, height: usize) -> Result<Self> {
+        unsafe {
+            let pbo = gl
+                .create_buffer()
+                .map_err(|e| format_err!("{:#}", e))
+                .context("Cannot create pixel pack buffer")?;
+
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, Some(pbo));
+            gl.buffer_data_size(
+                glow::PIXEL_PACK_BUFFER,
+                (width * height * std::mem::size_of::<f32>()) as i32,
+                glow::STREAM_READ,
+            );
+
+            gl.bind_texture(glow::TEXTURE_2D, Some(tex));
+            gl.get_tex_image(
+                glow::TEXTURE_2D,
+                0,
+                glow::RED,
+                glow::FLOAT,
+                PixelPackData::BufferOffset(0),
+            );
+
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+
+            let fence = gl
+                .fence_sync(glow::SYNC_GPU_COMMANDS_COMPLETE, 0)
+                .map_err(|e| format_err!("{:#}", e))
+                .context("Cannot create fence sync")?;
+
+            Ok(Self { pbo, fence, width, height })
+        }
+    }
+
+    /// Non-blockingly check whether the readback has completed. Returns
+    /// `Err(self)` while still in flight; the caller should try again next frame.
+    pub fn try_take(self, gl: &GlContext) -> std::result::Result<Option<Array2D>, Self> {
+        unsafe {
+            let status = gl.client_wait_sync(self.fence, 0, 0);
+            if status == glow::TIMEOUT_EXPIRED {
+                return Err(self);
+            }
+
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, Some(self.pbo));
+            let byte_len = self.width * self.height * std::mem::size_of::<f32>();
+            let ptr = gl.map_buffer_range(glow::PIXEL_PACK_BUFFER, 0, byte_len as i32, glow::MAP_READ_BIT);
+
+            let mut data = vec![0f32; self.width * self.height];
+            std::ptr::copy_nonoverlapping(ptr as *const f32, data.as_mut_ptr(), data.len());
+
+            gl.unmap_buffer(glow::PIXEL_PACK_BUFFER);
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+            gl.delete_sync(self.fence);
+            gl.delete_buffer(self.pbo);
+
+            Ok(Some(Array2D::from_array(self.width, data)))
+        }
+    }
+
+    /// Block until the readback resolves, polling `try_take` in a spin loop.
+    pub fn take_blocking(mut self, gl: &GlContext) -> Array2D {
+        loop {
+            self = match self.try_take(gl) {
+                Ok(Some(data)) => return data,
+                Ok(None) => unreachable!("try_take only returns None via Err"),
+                Err(handle) => handle,
+            };
+        }
+    }
+}
+
+/// Same PBO + fence idiom as `AsyncReadback`, but for a single `R32UI` texel
+/// (e.g. the residual accumulator from `create_u32_accumulator`) rather than
+/// a full `R32F` `Array2D`.
+pub struct AsyncTexelReadback {
+    pbo: NativeBuffer,
+    fence: NativeFence,
+}
+
+impl AsyncTexelReadback {
+    /// Issue an async readback of a single texel from `tex` (assumed `R32UI`, 1x1).
+    pub fn request(gl: &GlContext, tex: NativeTexture) -> Result<Self> {
+        unsafe {
+            let pbo = gl
+                .create_buffer()
+                .map_err(|e| format_err!("{:#}", e))
+                .context("Cannot create pixel pack buffer")?;
+
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, Some(pbo));
+            gl.buffer_data_size(glow::PIXEL_PACK_BUFFER, std::mem::size_of::<u32>() as i32, glow::STREAM_READ);
+
+            gl.bind_texture(glow::TEXTURE_2D, Some(tex));
+            gl.get_tex_image(
+                glow::TEXTURE_2D,
+                0,
+                glow::RED_INTEGER,
+                glow::UNSIGNED_INT,
+                PixelPackData::BufferOffset(0),
+            );
+
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+
+            let fence = gl
+                .fence_sync(glow::SYNC_GPU_COMMANDS_COMPLETE, 0)
+                .map_err(|e| format_err!("{:#}", e))
+                .context("Cannot create fence sync")?;
+
+            Ok(Self { pbo, fence })
+        }
+    }
+
+    /// Non-blockingly check whether the readback has completed.
+    pub fn try_take(self, gl: &GlContext) -> std::result::Result<Option<u32>, Self> {
+        unsafe {
+            let status = gl.client_wait_sync(self.fence, 0, 0);
+            if status == glow::TIMEOUT_EXPIRED {
+                return Err(self);
+            }
+
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, Some(self.pbo));
+            let ptr = gl.map_buffer_range(
+                glow::PIXEL_PACK_BUFFER,
+                0,
+                std::mem::size_of::<u32>() as i32,
+                glow::MAP_READ_BIT,
+            );
+
+            let mut bytes = [0u8; std::mem::size_of::<u32>()];
+            std::ptr::copy_nonoverlapping(ptr, bytes.as_mut_ptr(), bytes.len());
+
+            gl.unmap_buffer(glow::PIXEL_PACK_BUFFER);
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+            gl.delete_sync(self.fence);
+            gl.delete_buffer(self.pbo);
+
+            Ok(Some(u32::from_ne_bytes(bytes)))
+        }
+    }
+
+    /// Block until the readback resolves, polling `try_take` in a spin loop.
+    /// Only reasonable for a single texel, where the wait is a handful of
+    /// microseconds once the fence is actually signaled.
+    pub fn take_blocking(mut self, gl: &GlContext) -> u32 {
+        loop {
+            self = match self.try_take(gl) {
+                Ok(Some(value)) => return value,
+                Ok(None) => unreachable!("try_take only returns None via Err"),
+                Err(handle) => handle,
+            };
+        }
+    }
+}
+
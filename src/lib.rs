@@ -1,19 +1,19 @@
-use anyhow::{bail, format_err, Context as AnyhowContext, Result};
-use glow::{
-    Context as GlContext, HasContext, NativeBuffer, NativeProgram, NativeTexture, PixelPackData,
-};
-use std::path::Path;
+use anyhow::{bail, Result};
+
+mod backend;
+pub use backend::{ComputeBackend, ImageAccess, TileGeometry};
+
+mod gl_backend;
+pub use gl_backend::GlBackend;
+
+mod wgpu_backend;
+pub use wgpu_backend::WgpuBackend;
 
 mod gl_utils;
-use gl_utils::*;
 
 #[cfg(test)]
 mod test;
 
-/// Local size used in GPU kernels for X and for Y.
-/// `LOCAL_SIZE * LOCAL_SIZE < MAX_COMPUTE_WORK_GROUP_INVOCATIONS`
-const LOCAL_SIZE: usize = 32;
-
 /// Boundary condition settings
 #[repr(i32)]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -31,102 +31,142 @@ pub struct SimulationSize {
 }
 
 impl SimulationSize {
-    /// Calculate the dimensions of the simulation volume (including boundaries)
-    pub fn dims(&self) -> (usize, usize) {
-        (
-            self.x_tiles * LinSolve::TILE_OUTPUT_SIZE,
-            self.y_tiles * LinSolve::TILE_OUTPUT_SIZE,
-        )
+    /// Construct a simulation of the given tile counts.
+    pub fn from_tiles(x_tiles: usize, y_tiles: usize) -> Self {
+        Self { x_tiles, y_tiles }
+    }
+
+    /// Calculate the dimensions of the simulation volume (including boundaries).
+    /// `tile_output_size` is the solver's `TileGeometry::tile_output_size()`.
+    pub fn dims(&self, tile_output_size: usize) -> (usize, usize) {
+        (self.x_tiles * tile_output_size, self.y_tiles * tile_output_size)
     }
 }
 
-struct LinSolve {
+/// Jacobi linear solver, generic over the `ComputeBackend` that runs its
+/// kernel. `GlBackend` (raw `glow` + desktop GL) is the default and what
+/// every existing caller keeps using; `WgpuBackend` runs the same solve on
+/// `wgpu` (blocking, so not from a browser's WebGPU event loop).
+pub struct LinSolve<B: ComputeBackend> {
+    backend: B,
     /// Scratch space used in-between dispatches
-    sim_scratch: NativeTexture,
+    sim_scratch: B::Image,
     /// Scratch space used during dispatch (large!)
-    wg_scratch: NativeTexture,
-    program: NativeProgram,
+    wg_scratch: B::Image,
     size: SimulationSize,
 }
 
-impl LinSolve {
-    // NOTE: These constants MUST match those specified in the shaders
-
-    /// Number of steps each solver dispatch comprises
-    const STEPS_PER_DISPATCH: usize = 5;
-
+impl<B: ComputeBackend> LinSolve<B> {
     /// Number of dispatches per solver step
     const N_DISPATCHES: usize = 4;
 
-    /// Total steps per dispatch
-    const TOTAL_STEPS: usize = Self::STEPS_PER_DISPATCH * Self::N_DISPATCHES;
-
     /// Each tile outputs information with the given width
-    const TILE_OUTPUT_SIZE: usize = LOCAL_SIZE - Self::STEPS_PER_DISPATCH * 2;
+    fn tile_output_size(&self) -> usize {
+        self.backend.tile_geometry().tile_output_size()
+    }
 
-    /// Create a new solver (also creates scratch space)
-    pub fn new(gl: &GlContext, size: SimulationSize) -> Result<Self> {
-        let program = create_program(gl, &[(glow::COMPUTE_SHADER, "./kernels/lin_solve.comp")])?;
+    /// Total steps run per call to `step`
+    fn total_steps(&self) -> usize {
+        self.backend.tile_geometry().steps_per_dispatch * Self::N_DISPATCHES
+    }
+
+    /// Create a new solver on top of an already-constructed backend (also
+    /// creates scratch space sized to that backend's tile geometry).
+    pub fn new(backend: B, size: SimulationSize) -> Result<Self> {
+        let tile_geometry = backend.tile_geometry();
 
-        let workgroup_scratch = create_image(
-            gl,
-            (size.x_tiles * LOCAL_SIZE) as i32,
-            (size.y_tiles * LOCAL_SIZE) as i32,
+        let wg_scratch = backend.create_image(
+            size.x_tiles * tile_geometry.local_size,
+            size.y_tiles * tile_geometry.local_size,
             None,
         )?;
 
-        let (width, height) = size.dims();
-        let sim_scratch = create_image(gl, width as i32, height as i32, None)?;
+        let (width, height) = size.dims(tile_geometry.tile_output_size());
+        let sim_scratch = backend.create_image(width, height, None)?;
 
         Ok(Self {
-            program,
-            wg_scratch: workgroup_scratch,
+            backend,
+            wg_scratch,
             sim_scratch,
             size,
         })
     }
 
-    /// Solve the given system, returning the texture containing the result.
-    /// Return value may not be either of the two supplied textures!
+    /// Solve the given system, returning the image containing the result
+    /// and the number of Jacobi iterations actually run.
+    /// Return value may not be either of the two supplied images!
+    ///
+    /// If `tolerance` is `Some`, the global residual
+    /// (`r = x0 + a*Σneighbors - c*x_new`) is measured after each dispatch's
+    /// group of iterations, and the loop returns as soon as its magnitude
+    /// drops below the threshold rather than always running
+    /// `N_DISPATCHES` dispatches. Backends without a residual reduction path
+    /// (see `ComputeBackend::measure_residual`) reject tolerance mode.
     pub fn step(
         &self,
-        gl: &GlContext,
         b: Bounds,
-        x: NativeTexture,
-        x0: NativeTexture,
+        x: B::Image,
+        x0: B::Image,
         a: f32,
         c: f32,
-    ) -> Result<NativeTexture> {
+        tolerance: Option<f32>,
+    ) -> Result<(B::Image, usize)> {
         const X0_BIND: u32 = 0;
         const READ_BIND: u32 = 1;
         const WRITE_BIND: u32 = 2;
         const SCRATCH_BIND: u32 = 3;
 
-        unsafe {
-            gl.bind_image_texture(X0_BIND, x0, 0, false, 0, glow::READ_WRITE, glow::R32F);
-            gl.bind_image_texture(SCRATCH_BIND, self.wg_scratch, 0, false, 0, glow::READ_WRITE, glow::R32F);
-        }
+        self.backend.set_uniforms(a, c, b as i32);
+        self.backend.bind_image(X0_BIND, &x0, ImageAccess::ReadOnly);
+        self.backend
+            .bind_image(SCRATCH_BIND, &self.wg_scratch, ImageAccess::ReadWrite);
 
-        let mut write_tex = x;
+        let mut write_tex = x.clone();
         let mut read_tex;
+        let mut iterations_used = 0;
         for i in 0..Self::N_DISPATCHES {
             if i & 1 == 0 {
-                read_tex = x;
-                write_tex = self.sim_scratch;
+                read_tex = x.clone();
+                write_tex = self.sim_scratch.clone();
             } else {
-                read_tex = self.sim_scratch;
-                write_tex = x;
+                read_tex = self.sim_scratch.clone();
+                write_tex = x.clone();
             };
 
-            unsafe {
-                gl.bind_image_texture(READ_BIND, read_tex, 0, false, 0, glow::READ_WRITE, glow::R32F);
-                gl.bind_image_texture(WRITE_BIND, write_tex, 0, false, 0, glow::READ_WRITE, glow::R32F);
+            self.backend.bind_image(READ_BIND, &read_tex, ImageAccess::ReadOnly);
+            self.backend.bind_image(WRITE_BIND, &write_tex, ImageAccess::WriteOnly);
+
+            self.backend
+                .dispatch(self.size.x_tiles as u32, self.size.y_tiles as u32);
+            self.backend.memory_barrier();
+
+            iterations_used += self.backend.tile_geometry().steps_per_dispatch;
 
-                gl.dispatch_compute(self.size.x_tiles as u32, self.size.y_tiles as u32, 1);
-                gl.memory_barrier(glow::SHADER_STORAGE_BARRIER_BIT);
+            if let Some(tolerance) = tolerance {
+                match self.backend.measure_residual()? {
+                    Some(residual) if residual < tolerance => break,
+                    Some(_) => {}
+                    None => bail!("this backend does not support tolerance-based early exit"),
+                }
             }
         }
 
-        Ok(write_tex)
+        Ok((write_tex, iterations_used))
+    }
+
+    /// Create an image via this solver's backend, e.g. to build the `x`/`x0`
+    /// arguments to `step`.
+    pub fn create_image(
+        &self,
+        width: usize,
+        height: usize,
+        pixels: Option<&[f32]>,
+    ) -> Result<B::Image> {
+        self.backend.create_image(width, height, pixels)
+    }
+
+    /// Read an image (e.g. `step`'s result) back via this solver's backend.
+    pub fn download(&self, image: &B::Image, width: usize, height: usize) -> Vec<f32> {
+        self.backend.download(image, width, height)
     }
 }
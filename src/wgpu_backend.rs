@@ -0,0 +1,350 @@
+use crate::backend::{ComputeBackend, ImageAccess, TileGeometry};
+use anyhow::{bail, Context as AnyhowContext, Result};
+use std::cell::RefCell;
+use std::sync::Arc;
+
+const LIN_SOLVE_WGSL_TEMPLATE: &str = include_str!("../kernels/lin_solve.wgsl");
+
+/// WebGPU/WGSL backend: the same Jacobi kernel as `GlBackend`, ported to WGSL
+/// so it can run on Vulkan/Metal/DX12 through `wgpu`, without the hard
+/// SDL2/desktop-GL dependency `GlBackend` has.
+///
+/// `new` and `download` block the calling thread until `wgpu` finishes; there
+/// is no non-blocking entry point for an event-loop-driven host such as a
+/// browser's WebGPU.
+///
+/// `bind_image` is called once per binding ahead of `dispatch` (that's the
+/// shape `LinSolve::step` needs), but `wgpu` wants a whole bind group built
+/// at once; bindings are buffered in `pending_bindings` and assembled into a
+/// `BindGroup` lazily, inside `dispatch`.
+pub struct WgpuBackend {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::ComputePipeline,
+    bind_group_layout: wgpu::BindGroupLayout,
+    uniform_buffer: wgpu::Buffer,
+    tile_geometry: TileGeometry,
+    pending_bindings: RefCell<[Option<WgpuImage>; 4]>,
+}
+
+/// A storage-texture handle returned by `WgpuBackend::create_image`. Cheap to
+/// clone: an `Arc` around the underlying `wgpu::Texture` and its view.
+#[derive(Clone)]
+pub struct WgpuImage(Arc<WgpuImageInner>);
+
+struct WgpuImageInner {
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+    width: usize,
+    height: usize,
+}
+
+impl WgpuBackend {
+    /// Create a backend against the default adapter, specializing the WGSL
+    /// kernel for a tile size tuned to it. Blocks on `wgpu`'s async adapter
+    /// and device requests via `pollster`.
+    pub fn new() -> Result<Self> {
+        pollster::block_on(Self::new_async())
+    }
+
+    async fn new_async() -> Result<Self> {
+        let instance = wgpu::Instance::default();
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions::default())
+            .await
+            .context("No suitable wgpu adapter found")?;
+        // `DeviceDescriptor::default()` negotiates the WebGPU baseline limits
+        // (e.g. a 256-invocation `max_compute_invocations_per_workgroup`),
+        // which can be far smaller than what `detect_tile_geometry` below
+        // picks from the adapter's real capability -- request the adapter's
+        // actual limits so the device we get can run the tile we choose.
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: wgpu::Features::empty(),
+                    required_limits: adapter.limits(),
+                },
+                None,
+            )
+            .await
+            .context("Failed to open wgpu device")?;
+
+        let tile_geometry = Self::detect_tile_geometry(&adapter)?;
+
+        let source = LIN_SOLVE_WGSL_TEMPLATE
+            .replace("{{LOCAL_SIZE}}", &tile_geometry.local_size.to_string())
+            .replace("{{STEPS_PER_DISPATCH}}", &tile_geometry.steps_per_dispatch.to_string());
+
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("lin_solve"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("lin_solve_bind_group_layout"),
+            entries: &[
+                // Bindings are positional roles fixed by LinSolve::step
+                // (x0/read/write/scratch), so each gets the access mode that
+                // role actually uses in the kernel rather than a blanket
+                // ReadWrite.
+                storage_texture_entry(0, wgpu::StorageTextureAccess::ReadOnly),
+                storage_texture_entry(1, wgpu::StorageTextureAccess::ReadOnly),
+                storage_texture_entry(2, wgpu::StorageTextureAccess::WriteOnly),
+                storage_texture_entry(3, wgpu::StorageTextureAccess::ReadWrite),
+                wgpu::BindGroupLayoutEntry {
+                    binding: 4,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("lin_solve_pipeline_layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("lin_solve"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        // a, c, bounds, padded to a 16-byte uniform stride; written by
+        // `set_uniforms`, which `LinSolve::step` calls once before its
+        // dispatch loop.
+        let uniform_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("lin_solve_uniforms"),
+            size: std::mem::size_of::<[f32; 4]>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Ok(Self {
+            device,
+            queue,
+            pipeline,
+            bind_group_layout,
+            uniform_buffer,
+            tile_geometry,
+            pending_bindings: RefCell::new([None, None, None, None]),
+        })
+    }
+
+    /// `wgpu` has no exact analogue to sniffing `GL_VENDOR`; pick the largest
+    /// tile that fits the adapter's invocation-per-workgroup limit, capped at
+    /// the same 32x32 `GlBackend` uses on well-behaved drivers.
+    fn detect_tile_geometry(adapter: &wgpu::Adapter) -> Result<TileGeometry> {
+        let steps_per_dispatch = TileGeometry::DEFAULT_STEPS_PER_DISPATCH;
+        let max_invocations = adapter.limits().max_compute_invocations_per_workgroup as usize;
+        let max_local_size = (max_invocations as f64).sqrt() as usize;
+        if max_local_size <= steps_per_dispatch * 2 {
+            bail!(
+                "adapter's max_compute_invocations_per_workgroup ({}) is too small for a {}-step halo",
+                max_invocations,
+                steps_per_dispatch,
+            );
+        }
+
+        Ok(TileGeometry {
+            local_size: 32usize.min(max_local_size),
+            steps_per_dispatch,
+        })
+    }
+}
+
+fn storage_texture_entry(binding: u32, access: wgpu::StorageTextureAccess) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::StorageTexture {
+            access,
+            format: wgpu::TextureFormat::R32Float,
+            view_dimension: wgpu::TextureViewDimension::D2,
+        },
+        count: None,
+    }
+}
+
+impl ComputeBackend for WgpuBackend {
+    type Image = WgpuImage;
+
+    fn tile_geometry(&self) -> TileGeometry {
+        self.tile_geometry
+    }
+
+    fn create_image(
+        &self,
+        width: usize,
+        height: usize,
+        pixels: Option<&[f32]>,
+    ) -> Result<Self::Image> {
+        let size = wgpu::Extent3d {
+            width: width as u32,
+            height: height as u32,
+            depth_or_array_layers: 1,
+        };
+
+        let texture = self.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("lin_solve_image"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R32Float,
+            usage: wgpu::TextureUsages::STORAGE_BINDING
+                | wgpu::TextureUsages::COPY_SRC
+                | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let image = WgpuImage(Arc::new(WgpuImageInner {
+            texture,
+            view,
+            width,
+            height,
+        }));
+
+        if let Some(pixels) = pixels {
+            self.upload(&image, width, height, pixels);
+        }
+
+        Ok(image)
+    }
+
+    fn bind_image(&self, binding: u32, image: &Self::Image, _access: ImageAccess) {
+        self.pending_bindings.borrow_mut()[binding as usize] = Some(image.clone());
+    }
+
+    fn dispatch(&self, x_tiles: u32, y_tiles: u32) {
+        let pending = self.pending_bindings.borrow();
+        let bound: Vec<&WgpuImage> = pending
+            .iter()
+            .map(|slot| slot.as_ref().expect("all 4 storage image bindings must be set before dispatch"))
+            .collect();
+
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("lin_solve_bind_group"),
+            layout: &self.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&bound[0].0.view) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&bound[1].0.view) },
+                wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&bound[2].0.view) },
+                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&bound[3].0.view) },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: self.uniform_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("lin_solve_dispatch"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("lin_solve_pass"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups(x_tiles, y_tiles, 1);
+        }
+        self.queue.submit(Some(encoder.finish()));
+    }
+
+    fn memory_barrier(&self) {
+        // `wgpu` tracks resource usage per command submission and inserts
+        // whatever barriers/transitions the backing API needs between
+        // dispatches automatically; there's nothing for callers to do here.
+    }
+
+    fn set_uniforms(&self, a: f32, c: f32, bounds: i32) {
+        let mut bytes = [0u8; 16];
+        bytes[0..4].copy_from_slice(&a.to_ne_bytes());
+        bytes[4..8].copy_from_slice(&c.to_ne_bytes());
+        bytes[8..12].copy_from_slice(&bounds.to_ne_bytes());
+        self.queue.write_buffer(&self.uniform_buffer, 0, &bytes);
+    }
+
+    fn upload(&self, image: &Self::Image, width: usize, height: usize, pixels: &[f32]) {
+        self.queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &image.0.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            bytemuck::cast_slice(pixels),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some((width * std::mem::size_of::<f32>()) as u32),
+                rows_per_image: Some(height as u32),
+            },
+            wgpu::Extent3d {
+                width: width as u32,
+                height: height as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+
+    fn download(&self, image: &Self::Image, width: usize, height: usize) -> Vec<f32> {
+        let byte_len = (width * height * std::mem::size_of::<f32>()) as u64;
+        let readback_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("lin_solve_readback"),
+            size: byte_len,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("lin_solve_download") });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &image.0.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &readback_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some((width * std::mem::size_of::<f32>()) as u32),
+                    rows_per_image: Some(height as u32),
+                },
+            },
+            wgpu::Extent3d {
+                width: width as u32,
+                height: height as u32,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = readback_buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+        self.device.poll(wgpu::Maintain::Wait);
+        rx.recv()
+            .expect("map_async callback dropped without firing")
+            .expect("failed to map readback buffer");
+
+        let data = bytemuck::cast_slice(&slice.get_mapped_range()).to_vec();
+        readback_buffer.unmap();
+        data
+    }
+}